@@ -0,0 +1,116 @@
+use gl;
+
+/// Origin of a debug message reported through `GL_*_debug`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugSource {
+    /// Generated by calls to the GL API itself.
+    Api,
+    /// Generated by the window system (WGL/GLX/EGL/...).
+    WindowSystem,
+    /// Generated by the shader compiler.
+    ShaderCompiler,
+    /// Generated by a third-party library.
+    ThirdParty,
+    /// Generated by the application itself, through `glDebugMessageInsert`.
+    Application,
+    /// Anything else.
+    Other,
+}
+
+/// Category of a debug message reported through `GL_*_debug`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugMessageType {
+    /// An error, typically a GL error or invalid operation.
+    Error,
+    /// Use of deprecated behavior.
+    DeprecatedBehavior,
+    /// Undefined behavior.
+    UndefinedBehavior,
+    /// Portability issue.
+    Portability,
+    /// Performance issue.
+    Performance,
+    /// Anything else, including markers and push/pop groups.
+    Other,
+}
+
+/// Severity of a debug message reported through `GL_*_debug`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugSeverity {
+    /// Likely to cause undefined results, such as out-of-bounds access.
+    High,
+    /// A significant performance or correctness issue.
+    Medium,
+    /// A minor issue, such as redundant state changes.
+    Low,
+    /// Not an error, just an annotation (eg. object labels, push/pop groups).
+    Notification,
+}
+
+/// A single message reported by the driver's debug output extension.
+#[derive(Debug, Clone)]
+pub struct DebugMessage {
+    /// Where the message originated from.
+    pub source: DebugSource,
+    /// The kind of message this is.
+    pub message_type: DebugMessageType,
+    /// How severe the driver considers this message to be.
+    pub severity: DebugSeverity,
+    /// Driver- and source-specific message identifier.
+    pub id: gl::types::GLuint,
+    /// The message itself.
+    pub message: String,
+}
+
+/// What to do with each driver debug message.
+///
+/// Selected via `Context::set_debug_output_behavior`, defaults to `PanicOnError` which
+/// mirrors glium's historical behavior of crashing on driver-reported mistakes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugCallbackBehavior {
+    /// Debug messages are dropped entirely.
+    Ignore,
+    /// Every message is forwarded to the callback registered with
+    /// `Context::set_debug_callback`, if any.
+    LogViaCallback,
+    /// High or medium severity error-like messages cause a `panic!`. Everything else is
+    /// dropped. This is the default.
+    PanicOnError,
+}
+
+impl Default for DebugCallbackBehavior {
+    fn default() -> DebugCallbackBehavior {
+        DebugCallbackBehavior::PanicOnError
+    }
+}
+
+pub fn source_from_glenum(source: gl::types::GLenum) -> DebugSource {
+    match source {
+        gl::DEBUG_SOURCE_API => DebugSource::Api,
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => DebugSource::WindowSystem,
+        gl::DEBUG_SOURCE_SHADER_COMPILER => DebugSource::ShaderCompiler,
+        gl::DEBUG_SOURCE_THIRD_PARTY => DebugSource::ThirdParty,
+        gl::DEBUG_SOURCE_APPLICATION => DebugSource::Application,
+        _ => DebugSource::Other,
+    }
+}
+
+pub fn message_type_from_glenum(ty: gl::types::GLenum) -> DebugMessageType {
+    match ty {
+        gl::DEBUG_TYPE_ERROR => DebugMessageType::Error,
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => DebugMessageType::DeprecatedBehavior,
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => DebugMessageType::UndefinedBehavior,
+        gl::DEBUG_TYPE_PORTABILITY => DebugMessageType::Portability,
+        gl::DEBUG_TYPE_PERFORMANCE => DebugMessageType::Performance,
+        _ => DebugMessageType::Other,
+    }
+}
+
+pub fn severity_from_glenum(severity: gl::types::GLenum) -> DebugSeverity {
+    match severity {
+        gl::DEBUG_SEVERITY_HIGH => DebugSeverity::High,
+        gl::DEBUG_SEVERITY_MEDIUM => DebugSeverity::Medium,
+        gl::DEBUG_SEVERITY_LOW => DebugSeverity::Low,
+        _ => DebugSeverity::Notification,
+    }
+}
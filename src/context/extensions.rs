@@ -0,0 +1,95 @@
+use std::ffi::CStr;
+
+use gl;
+use version;
+use version::Api;
+
+/// List of OpenGL extensions supported by the current context, detected once at context
+/// creation time (see `get_extensions`).
+#[derive(Debug, Clone)]
+pub struct ExtensionsList {
+    pub gl_apple_vertex_array_object: bool,
+    pub gl_arb_buffer_storage: bool,
+    pub gl_arb_debug_output: bool,
+    pub gl_arb_depth_texture: bool,
+    pub gl_arb_fragment_shader: bool,
+    pub gl_arb_get_programy_binary: bool,
+    pub gl_arb_instanced_arrays: bool,
+    pub gl_arb_map_buffer_range: bool,
+    pub gl_arb_robustness: bool,
+    pub gl_arb_sampler_objects: bool,
+    pub gl_arb_shader_objects: bool,
+    pub gl_arb_sync: bool,
+    pub gl_arb_tessellation_shader: bool,
+    pub gl_arb_uniform_buffer_object: bool,
+    pub gl_arb_vertex_array_object: bool,
+    pub gl_arb_vertex_buffer_object: bool,
+    pub gl_arb_vertex_shader: bool,
+    pub gl_ext_framebuffer_blit: bool,
+    pub gl_ext_framebuffer_object: bool,
+    pub gl_ext_packed_depth_stencil: bool,
+    pub gl_ext_texture_integer: bool,
+    pub gl_khr_debug: bool,
+    pub gl_khr_robustness: bool,
+    pub gl_oes_vertex_array_object: bool,
+}
+
+/// Detects the list of extensions supported by the context that `gl` was loaded from.
+pub fn get_extensions(gl: &gl::Gl) -> ExtensionsList {
+    let strings = get_extensions_strings(gl);
+    let has = |name: &str| strings.iter().any(|ext| ext == name);
+
+    ExtensionsList {
+        gl_apple_vertex_array_object: has("GL_APPLE_vertex_array_object"),
+        gl_arb_buffer_storage: has("GL_ARB_buffer_storage"),
+        gl_arb_debug_output: has("GL_ARB_debug_output"),
+        gl_arb_depth_texture: has("GL_ARB_depth_texture"),
+        gl_arb_fragment_shader: has("GL_ARB_fragment_shader"),
+        gl_arb_get_programy_binary: has("GL_ARB_get_program_binary"),
+        gl_arb_instanced_arrays: has("GL_ARB_instanced_arrays"),
+        gl_arb_map_buffer_range: has("GL_ARB_map_buffer_range"),
+        gl_arb_robustness: has("GL_ARB_robustness"),
+        gl_arb_sampler_objects: has("GL_ARB_sampler_objects"),
+        gl_arb_shader_objects: has("GL_ARB_shader_objects"),
+        gl_arb_sync: has("GL_ARB_sync"),
+        gl_arb_tessellation_shader: has("GL_ARB_tessellation_shader"),
+        gl_arb_uniform_buffer_object: has("GL_ARB_uniform_buffer_object"),
+        gl_arb_vertex_array_object: has("GL_ARB_vertex_array_object"),
+        gl_arb_vertex_buffer_object: has("GL_ARB_vertex_buffer_object"),
+        gl_arb_vertex_shader: has("GL_ARB_vertex_shader"),
+        gl_ext_framebuffer_blit: has("GL_EXT_framebuffer_blit"),
+        gl_ext_framebuffer_object: has("GL_EXT_framebuffer_object"),
+        gl_ext_packed_depth_stencil: has("GL_EXT_packed_depth_stencil"),
+        gl_ext_texture_integer: has("GL_EXT_texture_integer"),
+        gl_khr_debug: has("GL_KHR_debug"),
+        gl_khr_robustness: has("GL_KHR_robustness"),
+        gl_oes_vertex_array_object: has("GL_OES_vertex_array_object"),
+    }
+}
+
+fn get_extensions_strings(gl: &gl::Gl) -> Vec<String> {
+    let version = version::get_gl_version(gl);
+
+    unsafe {
+        if version >= version::Version(Api::Gl, 3, 0) || version >= version::Version(Api::GlEs, 3, 0) {
+            let mut num_extensions = 0;
+            gl.GetIntegerv(gl::NUM_EXTENSIONS, &mut num_extensions);
+
+            (0 .. num_extensions).map(|i| {
+                let ptr = gl.GetStringi(gl::EXTENSIONS, i as gl::types::GLuint);
+                from_gl_string(ptr)
+            }).collect()
+        } else {
+            let ptr = gl.GetString(gl::EXTENSIONS);
+            from_gl_string(ptr).split(' ').map(|s| s.to_string()).collect()
+        }
+    }
+}
+
+unsafe fn from_gl_string(ptr: *const gl::types::GLubyte) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        String::from_utf8_lossy(CStr::from_ptr(ptr as *const _).to_bytes()).into_owned()
+    }
+}
@@ -7,9 +7,10 @@ use std::ptr;
 use std::collections::hash_state::DefaultState;
 use std::collections::HashMap;
 use std::default::Default;
-use std::cell::{RefCell, RefMut};
+use std::cell::{Cell, RefCell, RefMut};
 use std::ffi::CStr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::PathBuf;
+use std::sync::RwLock;
 
 use GliumCreationError;
 use backend::Backend;
@@ -17,17 +18,21 @@ use version;
 use version::Api;
 
 use fbo;
+use program_cache;
 use sampler_object;
 use uniforms;
 use util;
 use vertex_array_object;
 
 pub use self::capabilities::Capabilities;
+pub use self::debug::{DebugCallbackBehavior, DebugMessage, DebugMessageType, DebugSeverity,
+                       DebugSource};
 pub use self::extensions::ExtensionsList;
 pub use self::state::GLState;
 pub use version::Version as GlVersion;      // TODO: remove
 
 mod capabilities;
+mod debug;
 mod extensions;
 mod state;
 
@@ -42,6 +47,18 @@ pub struct Context {
     backend: RefCell<Box<Backend>>,
     check_current_context: bool,
 
+    // whether the backend actually created this context with robust access (checked via
+    // `GL_CONTEXT_FLAGS`/`GL_CONTEXT_FLAG_ROBUST_ACCESS_BIT`, not just extension presence,
+    // since the extension being listed only means the driver is *capable* of robustness,
+    // not that we opted into it); gates whether `glGetGraphicsResetStatus` is meaningful
+    robust_context: bool,
+
+    // set by `swap_buffers`/`get_reset_status` once a GPU reset has been detected through
+    // `GL_ARB_robustness`/`GL_KHR_robustness`; every GL object owned by this `Context` is
+    // then invalid, and `make_current` refuses to hand out further commands until `rebuild`
+    // has installed a fresh backend
+    context_lost: Cell<bool>,
+
     // we maintain a list of FBOs
     // the option is here to destroy the container
     pub framebuffer_objects: Option<fbo::FramebuffersContainer>,
@@ -49,8 +66,18 @@ pub struct Context {
     pub vertex_array_objects: vertex_array_object::VertexAttributesSystem,
 
     // we maintain a list of samplers for each possible behavior
-    pub samplers: RefCell<HashMap<uniforms::SamplerBehavior, sampler_object::SamplerObject, 
+    pub samplers: RefCell<HashMap<uniforms::SamplerBehavior, sampler_object::SamplerObject,
                           DefaultState<util::FnvHasher>>>,
+
+    // on backends without sampler object support, `sampler_object::apply_emulated_sampler`
+    // sets texture parameters directly; this remembers what was last applied to each
+    // texture so that we don't reissue the same `TexParameter*` calls every draw
+    pub samplers_behaviors: RefCell<HashMap<gl::types::GLuint, uniforms::SamplerBehavior,
+                             DefaultState<util::FnvHasher>>>,
+
+    // cache of linked program binaries, keyed by a digest of their sources; see
+    // `set_program_cache_dir`
+    pub program_cache: RefCell<program_cache::ProgramCache>,
 }
 
 pub struct CommandContext<'a, 'b> {
@@ -64,20 +91,49 @@ pub struct CommandContext<'a, 'b> {
 
 /// Struct shared with the debug output callback.
 pub struct SharedDebugOutput {
-    /// Whether debug output should report errors
-    pub report_errors: AtomicBool,
+    /// User-registered callback that messages are forwarded to when `behavior` is
+    /// `LogViaCallback`.
+    pub callback: RwLock<Option<Box<Fn(DebugMessage) + Send>>>,
+    /// What to do with each message; the callback above is only called under
+    /// `LogViaCallback`.
+    pub behavior: RwLock<DebugCallbackBehavior>,
 }
 
 impl SharedDebugOutput {
     pub fn new() -> Box<SharedDebugOutput> {
         Box::new(SharedDebugOutput {
-            report_errors: AtomicBool::new(true),
+            callback: RwLock::new(None),
+            behavior: RwLock::new(DebugCallbackBehavior::default()),
         })
     }
 }
 
+/// Status of a context as reported by `glGetGraphicsResetStatus`, part of the
+/// `GL_ARB_robustness`/`GL_KHR_robustness` extensions.
+///
+/// Once a context reports anything other than `NoError`, every GL object that was created
+/// with it is lost; the only way forward is to create a fresh backend and call
+/// `Context::rebuild`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ContextResetStatus {
+    /// No reset has been detected; the context is healthy.
+    NoError,
+    /// This context caused the reset (eg. an infinite shader loop, a driver bug triggered
+    /// by one of our own calls).
+    Guilty,
+    /// Another context sharing the GPU caused the reset.
+    Innocent,
+    /// A reset was detected but the driver couldn't determine the cause.
+    Unknown,
+}
+
 impl Context {
-    pub fn new<B>(backend: B, check_current_context: bool)
+    /// `debug_callback_behavior` selects what happens to driver debug messages from the
+    /// moment the context is created; pass `None` to keep the default
+    /// (`DebugCallbackBehavior::PanicOnError`). It can still be changed afterwards with
+    /// `set_debug_output_behavior`.
+    pub fn new<B>(backend: B, check_current_context: bool,
+                  debug_callback_behavior: Option<DebugCallbackBehavior>)
                   -> Result<Context, GliumCreationError>
                   where B: Backend + 'static
     {
@@ -90,6 +146,12 @@ impl Context {
         let capabilities = capabilities::get_capabilities(&gl, &version, &extensions);
 
         let shared_debug = SharedDebugOutput::new();
+        if let Some(behavior) = debug_callback_behavior {
+            *shared_debug.behavior.write().unwrap() = behavior;
+        }
+
+        let robust_context = (extensions.gl_arb_robustness || extensions.gl_khr_robustness) &&
+                              has_robust_access_flag(&gl);
 
         {
             let mut ctxt = CommandContext {
@@ -114,9 +176,13 @@ impl Context {
             shared_debug_output: shared_debug,
             backend: RefCell::new(Box::new(backend)),
             check_current_context: check_current_context,
+            robust_context: robust_context,
+            context_lost: Cell::new(false),
             framebuffer_objects: Some(fbo::FramebuffersContainer::new()),
             vertex_array_objects: vertex_array_object::VertexAttributesSystem::new(),
             samplers: RefCell::new(HashMap::with_hash_state(Default::default())),
+            samplers_behaviors: RefCell::new(HashMap::with_hash_state(Default::default())),
+            program_cache: RefCell::new(program_cache::ProgramCache::new()),
         })
     }
 
@@ -125,6 +191,20 @@ impl Context {
     }
 
     pub fn make_current<'a>(&'a self) -> CommandContext<'a, 'a> {
+        self.try_make_current().expect("attempted to use a Context after a GPU reset was \
+            detected; use `Context::try_make_current` (or check `is_context_lost`/\
+            `get_reset_status` beforehand) to recover instead of panicking")
+    }
+
+    /// Like `make_current`, but returns `Err(GliumCreationError::ContextLost)` instead of
+    /// panicking if a GPU reset has invalidated this context (see `get_reset_status`).
+    /// Long-running applications can use this to recover by creating a new backend and
+    /// calling `rebuild`, instead of crashing the moment a TDR or GPU hang is detected.
+    pub fn try_make_current<'a>(&'a self) -> Result<CommandContext<'a, 'a>, GliumCreationError> {
+        if self.context_lost.get() {
+            return Err(GliumCreationError::ContextLost);
+        }
+
         if self.check_current_context {
             let backend = self.backend.borrow();
             if !backend.is_current() {
@@ -132,14 +212,14 @@ impl Context {
             }
         }
 
-        CommandContext {
+        Ok(CommandContext {
             gl: &self.gl,
             state: self.state.borrow_mut(),
             version: &self.version,
             extensions: &self.extensions,
             capabilities: &self.capabilities,
             shared_debug_output: &*self.shared_debug_output,
-        }
+        })
     }
 
     pub fn rebuild<B>(&self, new_backend: B)
@@ -149,6 +229,7 @@ impl Context {
         unsafe { new_backend.make_current() };
 
         *self.state.borrow_mut() = Default::default();
+        self.context_lost.set(false);
         // FIXME: verify version, capabilities and extensions
         *self.backend.borrow_mut() = Box::new(new_backend);
 
@@ -169,6 +250,71 @@ impl Context {
 
         // swapping
         backend.swap_buffers();
+
+        // polling for GPU resets (TDR, driver crashes, ...) so that callers stop issuing
+        // commands against a context the driver has already thrown away, instead of a
+        // confusing wall of GL errors on every subsequent call
+        if self.is_robust() {
+            if let Some(status) = self.poll_reset_status() {
+                if status != ContextResetStatus::NoError {
+                    self.context_lost.set(true);
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if this context actually has GPU-reset detection enabled: the driver
+    /// supports `GL_ARB_robustness`/`GL_KHR_robustness` *and* the backend created this
+    /// context with robust access (`GL_CONTEXT_FLAG_ROBUST_ACCESS_BIT` set in
+    /// `GL_CONTEXT_FLAGS`). Extension support alone doesn't imply this: a context created
+    /// without requesting robust access will never report anything through
+    /// `glGetGraphicsResetStatus`.
+    pub fn is_robust(&self) -> bool {
+        self.robust_context
+    }
+
+    /// Returns `true` if a GPU reset has been detected and every GL object owned by this
+    /// `Context` is therefore invalid. The only way forward is to create a fresh backend
+    /// and call `rebuild`.
+    pub fn is_context_lost(&self) -> bool {
+        self.context_lost.get()
+    }
+
+    /// Queries the driver for the current reset status, bypassing the cached
+    /// `is_context_lost` flag. Returns `None` if this context doesn't support robustness.
+    pub fn get_reset_status(&self) -> Option<ContextResetStatus> {
+        let status = self.poll_reset_status();
+
+        if let Some(status) = status {
+            if status != ContextResetStatus::NoError {
+                self.context_lost.set(true);
+            }
+        }
+
+        status
+    }
+
+    fn poll_reset_status(&self) -> Option<ContextResetStatus> {
+        if !self.is_robust() {
+            return None;
+        }
+
+        let status = unsafe {
+            if self.version >= GlVersion(Api::Gl, 4, 5) {
+                self.gl.GetGraphicsResetStatus()
+            } else if self.extensions.gl_khr_robustness {
+                self.gl.GetGraphicsResetStatusKHR()
+            } else {
+                self.gl.GetGraphicsResetStatusARB()
+            }
+        };
+
+        Some(match status {
+            gl::NO_ERROR => ContextResetStatus::NoError,
+            gl::GUILTY_CONTEXT_RESET => ContextResetStatus::Guilty,
+            gl::INNOCENT_CONTEXT_RESET => ContextResetStatus::Innocent,
+            _ => ContextResetStatus::Unknown,
+        })
     }
 
     pub fn capabilities(&self) -> &Capabilities {
@@ -182,6 +328,46 @@ impl Context {
     pub fn get_extensions(&self) -> &ExtensionsList {
         &self.extensions
     }
+
+    /// Registers a callback that every debug message reported by the driver is forwarded
+    /// to. Has no effect unless the debug output behavior is `DebugCallbackBehavior::LogViaCallback`
+    /// (see `set_debug_output_behavior`).
+    pub fn set_debug_callback<F>(&self, callback: F) where F: Fn(DebugMessage) + Send + 'static {
+        *self.shared_debug_output.callback.write().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Changes what happens to debug messages reported by the driver. Defaults to
+    /// `DebugCallbackBehavior::PanicOnError`, which mirrors glium's historical behavior.
+    pub fn set_debug_output_behavior(&self, behavior: DebugCallbackBehavior) {
+        *self.shared_debug_output.behavior.write().unwrap() = behavior;
+    }
+
+    /// Sets the directory used to persist the program binary cache across runs (see
+    /// `program_cache::ProgramCache`). Without a directory, cached binaries only live for
+    /// the lifetime of this `Context`.
+    pub fn set_program_cache_dir<P: Into<PathBuf>>(&self, directory: P) {
+        self.program_cache.borrow_mut().set_directory(directory.into());
+    }
+
+    /// Enables or disables `GL_DEBUG_OUTPUT_SYNCHRONOUS`. Synchronous output guarantees
+    /// that the callback runs on the thread and in the order that triggered it, which is
+    /// convenient but can noticeably hurt performance; turn it off if the callback only
+    /// logs or otherwise doesn't need to be precisely attributed to a GL call.
+    pub fn set_debug_output_synchronous(&self, synchronous: bool) {
+        let mut ctxt = self.make_current();
+
+        if ctxt.state.enabled_debug_output_synchronous != synchronous {
+            unsafe {
+                if synchronous {
+                    ctxt.gl.Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+                } else {
+                    ctxt.gl.Disable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+                }
+            }
+
+            ctxt.state.enabled_debug_output_synchronous = synchronous;
+        }
+    }
 }
 
 impl Drop for Context {
@@ -231,6 +417,19 @@ impl Drop for Context {
     }
 }
 
+// Checks `GL_CONTEXT_FLAGS` for `GL_CONTEXT_FLAG_ROBUST_ACCESS_BIT`. Listing
+// `GL_ARB_robustness`/`GL_KHR_robustness` only means the driver is capable of robustness;
+// `glGetGraphicsResetStatus` is only meaningful if the backend actually requested a robust
+// context (eg. `WGL_CONTEXT_ROBUST_ACCESS_BIT_ARB`/`EGL_CONTEXT_OPENGL_ROBUST_ACCESS_EXT`
+// at context creation), which this flag reflects.
+fn has_robust_access_flag(gl: &gl::Gl) -> bool {
+    unsafe {
+        let mut flags = 0;
+        gl.GetIntegerv(gl::CONTEXT_FLAGS, &mut flags);
+        (flags as gl::types::GLuint & gl::CONTEXT_FLAG_ROBUST_ACCESS_BIT) != 0
+    }
+}
+
 fn check_gl_compatibility(ctxt: &mut CommandContext) -> Result<(), GliumCreationError> {
     let mut result = Vec::new();
 
@@ -358,19 +557,44 @@ fn init_debug_callback(mut ctxt: &mut CommandContext) {
         let user_param = user_param as *const SharedDebugOutput;
         let user_param = unsafe { user_param.as_ref().unwrap() };
 
-        if (severity == gl::DEBUG_SEVERITY_HIGH || severity == gl::DEBUG_SEVERITY_MEDIUM) && 
-           (ty == gl::DEBUG_TYPE_ERROR || ty == gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR ||
-            ty == gl::DEBUG_TYPE_PORTABILITY || ty == gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR)
-        {
-            if user_param.report_errors.load(Ordering::Relaxed) {
-                let message = unsafe {
-                    String::from_utf8(CStr::from_ptr(message).to_bytes().to_vec()).unwrap()
-                };
-
-                panic!("Debug message with high or medium severity: `{}`.\n\
-                        Please report this error: https://github.com/tomaka/glium/issues",
-                        message);
-            }
+        let behavior = *user_param.behavior.read().unwrap();
+
+        match behavior {
+            DebugCallbackBehavior::Ignore => (),
+
+            DebugCallbackBehavior::LogViaCallback => {
+                if let Some(ref callback) = *user_param.callback.read().unwrap() {
+                    let message = unsafe {
+                        String::from_utf8(CStr::from_ptr(message).to_bytes().to_vec()).unwrap()
+                    };
+
+                    callback(DebugMessage {
+                        source: debug::source_from_glenum(source),
+                        message_type: debug::message_type_from_glenum(ty),
+                        severity: debug::severity_from_glenum(severity),
+                        id: id,
+                        message: message,
+                    });
+                }
+            },
+
+            DebugCallbackBehavior::PanicOnError => {
+                let is_error_like = ty == gl::DEBUG_TYPE_ERROR ||
+                    ty == gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR ||
+                    ty == gl::DEBUG_TYPE_PORTABILITY || ty == gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR;
+                let is_high_severity = severity == gl::DEBUG_SEVERITY_HIGH ||
+                    severity == gl::DEBUG_SEVERITY_MEDIUM;
+
+                if is_error_like && is_high_severity {
+                    let message = unsafe {
+                        String::from_utf8(CStr::from_ptr(message).to_bytes().to_vec()).unwrap()
+                    };
+
+                    panic!("Debug message with high or medium severity: `{}`.\n\
+                            Please report this error: https://github.com/tomaka/glium/issues",
+                            message);
+                }
+            },
         }
     }
 
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::mem;
+use std::path::PathBuf;
+
+use gl;
+use context::CommandContext;
+use util::FnvHasher;
+use version::Version;
+use version::Api;
+
+struct CachedBinary {
+    format: gl::types::GLenum,
+    data: Vec<u8>,
+}
+
+/// Caches linked program binaries obtained through `glGetProgramBinary`, so that
+/// recompiling the same shaders (typically on every application startup) doesn't re-pay
+/// the driver's compile/link cost.
+///
+/// Entries live in memory for the lifetime of the cache, and are additionally persisted
+/// to disk if a directory has been set with `Context::set_program_cache_dir`. Requires
+/// `GL_ARB_get_program_binary` (core since GL 4.1); see the `gl_program_binary` check in
+/// `check_gl_compatibility`.
+pub struct ProgramCache {
+    directory: Option<PathBuf>,
+    in_memory: HashMap<u64, CachedBinary>,
+}
+
+impl ProgramCache {
+    pub fn new() -> ProgramCache {
+        ProgramCache {
+            directory: None,
+            in_memory: HashMap::new(),
+        }
+    }
+
+    pub fn set_directory(&mut self, directory: PathBuf) {
+        self.directory = Some(directory);
+    }
+
+    /// Computes the cache key for a program built from the given (concatenated per-stage)
+    /// shader sources and the current driver's vendor/renderer/version strings, so that a
+    /// driver or GPU change invalidates every entry instead of handing `glProgramBinary`
+    /// data the new driver doesn't recognize.
+    pub fn digest(ctxt: &CommandContext, sources: &[&str]) -> u64 {
+        digest_with_driver_info(sources, &gl_string(ctxt, gl::VENDOR),
+                                &gl_string(ctxt, gl::RENDERER), &gl_string(ctxt, gl::VERSION))
+    }
+
+    /// Looks up a previously-cached binary, falling back to the on-disk cache directory
+    /// (if any) on an in-memory miss.
+    pub fn get(&mut self, key: u64) -> Option<(gl::types::GLenum, Vec<u8>)> {
+        if let Some(cached) = self.in_memory.get(&key) {
+            return Some((cached.format, cached.data.clone()));
+        }
+
+        let raw = {
+            let path = match self.directory {
+                Some(ref dir) => dir.join(format!("{:016x}.bin", key)),
+                None => return None,
+            };
+
+            let mut raw = Vec::new();
+            match File::open(&path).and_then(|mut f| f.read_to_end(&mut raw)) {
+                Ok(_) if raw.len() > 4 => raw,
+                _ => return None,
+            }
+        };
+
+        let format = unsafe {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&raw[.. 4]);
+            mem::transmute::<[u8; 4], gl::types::GLenum>(bytes)
+        };
+        let data = raw[4 ..].to_vec();
+
+        self.in_memory.insert(key, CachedBinary { format: format, data: data.clone() });
+        Some((format, data))
+    }
+
+    /// Stores a newly-linked program's binary, both in memory and, if a directory has
+    /// been configured, on disk.
+    pub fn insert(&mut self, key: u64, format: gl::types::GLenum, data: Vec<u8>) {
+        if let Some(ref dir) = self.directory {
+            if fs::create_dir_all(dir).is_ok() {
+                if let Ok(mut file) = File::create(dir.join(format!("{:016x}.bin", key))) {
+                    let format_bytes: [u8; 4] = unsafe { mem::transmute(format) };
+                    let _ = file.write_all(&format_bytes);
+                    let _ = file.write_all(&data);
+                }
+            }
+        }
+
+        self.in_memory.insert(key, CachedBinary { format: format, data: data });
+    }
+}
+
+/// Does the actual digest computation for `ProgramCache::digest`, split out so it can be
+/// unit-tested without a real GL context (the driver identity strings are just inputs).
+fn digest_with_driver_info(sources: &[&str], vendor: &str, renderer: &str, version: &str) -> u64 {
+    let mut hasher = FnvHasher::default();
+
+    for source in sources {
+        source.hash(&mut hasher);
+    }
+
+    vendor.hash(&mut hasher);
+    renderer.hash(&mut hasher);
+    version.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// Builds `program` from `sources`, either by loading a previously-cached binary or, on a
+/// cache miss, by calling `compile` to link it normally and then storing the result.
+///
+/// This is the single entry point program-build code should call: it's the only function
+/// in this module that needs to be wired into program creation for the cache to actually
+/// take effect (`ProgramCache::get`/`insert`, `get_binary` and `load_binary` are the
+/// pieces it's built from, and shouldn't normally be called directly).
+///
+/// Falls through to plain `compile(ctxt, program)` with no caching at all if the backend
+/// doesn't satisfy the same `gl_program_binary`/`GL_ARB_get_program_binary` check that
+/// `check_gl_compatibility` enforces at context-creation time (that check only rejects the
+/// context when the `gl_program_binary` feature is enabled, so a context built without the
+/// feature reaches here with no such guarantee).
+pub fn build_with_cache<F>(ctxt: &mut CommandContext, cache: &mut ProgramCache,
+                           program: gl::types::GLuint, sources: &[&str], compile: F)
+    where F: FnOnce(&mut CommandContext, gl::types::GLuint)
+{
+    if !is_program_binary_supported(ctxt) {
+        compile(ctxt, program);
+        return;
+    }
+
+    let key = ProgramCache::digest(ctxt, sources);
+
+    if let Some((format, data)) = cache.get(key) {
+        if load_binary(ctxt, program, format, &data) {
+            return;
+        }
+    }
+
+    compile(ctxt, program);
+
+    let (format, data) = get_binary(ctxt, program);
+    cache.insert(key, format, data);
+}
+
+fn is_program_binary_supported(ctxt: &CommandContext) -> bool {
+    cfg!(feature = "gl_program_binary") &&
+    (ctxt.version >= &Version(Api::Gl, 4, 1) || ctxt.extensions.gl_arb_get_programy_binary)
+}
+
+/// Retrieves the linked binary of `program` via `glGetProgramBinary`, ready to be stored
+/// in a `ProgramCache`.
+pub fn get_binary(ctxt: &mut CommandContext, program: gl::types::GLuint)
+                  -> (gl::types::GLenum, Vec<u8>)
+{
+    unsafe {
+        let mut len = 0;
+        ctxt.gl.GetProgramiv(program, gl::PROGRAM_BINARY_LENGTH, &mut len);
+
+        let mut data = vec![0u8; len as usize];
+        let mut format = 0;
+        let mut written = 0;
+        ctxt.gl.GetProgramBinary(program, len, &mut written, &mut format,
+                                 data.as_mut_ptr() as *mut _);
+        data.truncate(written as usize);
+
+        (format as gl::types::GLenum, data)
+    }
+}
+
+/// Attempts to load a cached binary directly into `program` via `glProgramBinary`,
+/// skipping the driver's compile/link step entirely. Returns whether linking succeeded;
+/// on `false` the caller should fall back to compiling from source, since a driver update
+/// can reject a binary format or layout it used to accept.
+pub fn load_binary(ctxt: &mut CommandContext, program: gl::types::GLuint,
+                   format: gl::types::GLenum, data: &[u8]) -> bool
+{
+    unsafe {
+        ctxt.gl.ProgramBinary(program, format, data.as_ptr() as *const _,
+                              data.len() as gl::types::GLsizei);
+
+        let mut link_status = 0;
+        ctxt.gl.GetProgramiv(program, gl::LINK_STATUS, &mut link_status);
+        link_status != 0
+    }
+}
+
+fn gl_string(ctxt: &CommandContext, name: gl::types::GLenum) -> String {
+    unsafe {
+        let ptr = ctxt.gl.GetString(name) as *const i8;
+        if ptr.is_null() {
+            String::new()
+        } else {
+            String::from_utf8_lossy(CStr::from_ptr(ptr).to_bytes()).into_owned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_is_deterministic() {
+        let a = digest_with_driver_info(&["a", "b"], "V", "R", "1.0");
+        let b = digest_with_driver_info(&["a", "b"], "V", "R", "1.0");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn digest_changes_with_sources() {
+        let a = digest_with_driver_info(&["void main() {}"], "V", "R", "1.0");
+        let b = digest_with_driver_info(&["void main() { discard; }"], "V", "R", "1.0");
+        assert!(a != b);
+    }
+
+    #[test]
+    fn digest_changes_with_driver_info() {
+        let a = digest_with_driver_info(&["void main() {}"], "Vendor A", "R", "1.0");
+        let b = digest_with_driver_info(&["void main() {}"], "Vendor B", "R", "1.0");
+        assert!(a != b);
+    }
+
+    #[test]
+    fn missing_key_returns_none() {
+        let mut cache = ProgramCache::new();
+        assert_eq!(cache.get(123), None);
+    }
+
+    #[test]
+    fn in_memory_round_trip() {
+        let mut cache = ProgramCache::new();
+        cache.insert(42, 0x1908, vec![1, 2, 3, 4]);
+        assert_eq!(cache.get(42), Some((0x1908, vec![1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn on_disk_round_trip() {
+        let dir = std::env::temp_dir().join("glium_program_cache_test_on_disk_round_trip");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut writer = ProgramCache::new();
+        writer.set_directory(dir.clone());
+        writer.insert(7, 0x1907, vec![9, 9, 9]);
+
+        // a fresh cache with no in-memory entries must still find the binary on disk
+        let mut reader = ProgramCache::new();
+        reader.set_directory(dir.clone());
+        assert_eq!(reader.get(7), Some((0x1907, vec![9, 9, 9])));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
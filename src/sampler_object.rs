@@ -52,6 +52,24 @@ impl SamplerObject {
 
                 ctxt.gl.SamplerParameterf(sampler, gl::TEXTURE_MAX_ANISOTROPY_EXT, value);
             }
+
+            ctxt.gl.SamplerParameterf(sampler, gl::TEXTURE_MIN_LOD, behavior.min_lod);
+            ctxt.gl.SamplerParameterf(sampler, gl::TEXTURE_MAX_LOD, behavior.max_lod);
+            ctxt.gl.SamplerParameterf(sampler, gl::TEXTURE_LOD_BIAS, behavior.lod_bias);
+
+            if let Some(border_color) = behavior.border_color {
+                let border_color = [border_color.0, border_color.1, border_color.2,
+                                     border_color.3];
+                ctxt.gl.SamplerParameterfv(sampler, gl::TEXTURE_BORDER_COLOR,
+                                           border_color.as_ptr());
+            }
+
+            if let Some(func) = behavior.depth_texture_comparison {
+                ctxt.gl.SamplerParameteri(sampler, gl::TEXTURE_COMPARE_MODE,
+                                          gl::COMPARE_REF_TO_TEXTURE as gl::types::GLint);
+                ctxt.gl.SamplerParameteri(sampler, gl::TEXTURE_COMPARE_FUNC,
+                                          func.to_glenum() as gl::types::GLint);
+            }
         }
 
         SamplerObject {
@@ -108,3 +126,135 @@ pub fn get_sampler(ctxt: &mut CommandContext,
     samplers.insert(behavior.clone(), sampler);
     Ok(id)
 }
+
+/// The result of resolving a `SamplerBehavior` into something that can be applied before
+/// a draw call.
+pub enum SamplerResult {
+    /// A sampler object should be bound with `glBindSampler`.
+    Object(gl::types::GLuint),
+    /// Sampler objects aren't supported by this backend. The given behavior must instead
+    /// be applied directly on the texture that is about to be bound, with `TexParameter*`.
+    Emulated(SamplerBehavior),
+}
+
+/// Returns the sampler object corresponding to the given behavior, or the behavior to
+/// emulate through `TexParameter*` calls if sampler objects are not supported by this
+/// backend. Unlike `get_sampler`, this never fails a draw call.
+pub fn get_sampler_or_emulate(ctxt: &mut CommandContext,
+                              samplers: &mut HashMap<SamplerBehavior, SamplerObject>,
+                              behavior: &SamplerBehavior)
+                              -> SamplerResult
+{
+    // falling back to per-texture parameters when samplers aren't supported
+    if ctxt.version < &Version(Api::Gl, 3, 2) && !ctxt.extensions.gl_arb_sampler_objects {
+        return SamplerResult::Emulated(*behavior);
+    }
+
+    // looking for an existing sampler
+    if let Some(obj) = samplers.get(behavior) {
+        return SamplerResult::Object(obj.get_id());
+    }
+
+    // builds a new sampler
+    let sampler = SamplerObject::new(ctxt, behavior);
+    let id = sampler.get_id();
+    samplers.insert(behavior.clone(), sampler);
+    SamplerResult::Object(id)
+}
+
+/// Applies a sampler's behavior directly on the texture currently bound to `target`, for
+/// backends that don't support sampler objects (see `get_sampler_or_emulate`).
+///
+/// `last_applied` is a per-texture cache of the behavior that was last applied; if it
+/// already matches `behavior`, the `TexParameter*` calls are skipped entirely.
+pub fn apply_emulated_sampler(ctxt: &mut CommandContext, target: gl::types::GLenum,
+                              texture: gl::types::GLuint,
+                              last_applied: &mut HashMap<gl::types::GLuint, SamplerBehavior>,
+                              behavior: &SamplerBehavior)
+{
+    if last_applied.get(&texture) == Some(behavior) {
+        return;
+    }
+
+    unsafe {
+        ctxt.gl.TexParameteri(target, gl::TEXTURE_WRAP_S,
+                              behavior.wrap_function.0.to_glenum() as gl::types::GLint);
+        ctxt.gl.TexParameteri(target, gl::TEXTURE_WRAP_T,
+                              behavior.wrap_function.1.to_glenum() as gl::types::GLint);
+        ctxt.gl.TexParameteri(target, gl::TEXTURE_WRAP_R,
+                              behavior.wrap_function.2.to_glenum() as gl::types::GLint);
+        ctxt.gl.TexParameteri(target, gl::TEXTURE_MIN_FILTER,
+                              behavior.minify_filter.to_glenum() as gl::types::GLint);
+        ctxt.gl.TexParameteri(target, gl::TEXTURE_MAG_FILTER,
+                              behavior.magnify_filter.to_glenum() as gl::types::GLint);
+
+        if let Some(max_value) = ctxt.capabilities.max_texture_max_anisotropy {
+            let value = if behavior.max_anisotropy as f32 > max_value {
+                max_value
+            } else {
+                behavior.max_anisotropy as f32
+            };
+
+            ctxt.gl.TexParameterf(target, gl::TEXTURE_MAX_ANISOTROPY_EXT, value);
+        }
+
+        ctxt.gl.TexParameterf(target, gl::TEXTURE_MIN_LOD, behavior.min_lod);
+        ctxt.gl.TexParameterf(target, gl::TEXTURE_MAX_LOD, behavior.max_lod);
+        ctxt.gl.TexParameterf(target, gl::TEXTURE_LOD_BIAS, behavior.lod_bias);
+
+        match behavior.border_color {
+            Some(border_color) => {
+                let border_color = [border_color.0, border_color.1, border_color.2,
+                                     border_color.3];
+                ctxt.gl.TexParameterfv(target, gl::TEXTURE_BORDER_COLOR, border_color.as_ptr());
+            },
+            // a texture previously used with a border color must be reset to the GL
+            // default, or a later `BorderClamp` draw with `border_color: None` would keep
+            // sampling the stale color instead of the default transparent black
+            None => {
+                let default_border_color = [0.0f32, 0.0, 0.0, 0.0];
+                ctxt.gl.TexParameterfv(target, gl::TEXTURE_BORDER_COLOR,
+                                       default_border_color.as_ptr());
+            },
+        }
+
+        match behavior.depth_texture_comparison {
+            Some(func) => {
+                ctxt.gl.TexParameteri(target, gl::TEXTURE_COMPARE_MODE,
+                                      gl::COMPARE_REF_TO_TEXTURE as gl::types::GLint);
+                ctxt.gl.TexParameteri(target, gl::TEXTURE_COMPARE_FUNC,
+                                      func.to_glenum() as gl::types::GLint);
+            },
+            // same as above: a texture that was sampled as a shadow map and is now being
+            // sampled normally must have comparison turned back off, or every subsequent
+            // read silently turns into a 0/1 comparison result instead of the raw value
+            None => {
+                ctxt.gl.TexParameteri(target, gl::TEXTURE_COMPARE_MODE,
+                                      gl::NONE as gl::types::GLint);
+            },
+        }
+    }
+
+    last_applied.insert(texture, *behavior);
+}
+
+/// Resolves and applies the sampler to use for the texture currently bound to `target`,
+/// transparently handling both codepaths: binds a sampler object with `glBindSampler` when
+/// supported, or falls back to emulating it with `TexParameter*` on the texture itself via
+/// `apply_emulated_sampler` otherwise. Draw code should call this instead of using
+/// `get_sampler`/`get_sampler_or_emulate`/`apply_emulated_sampler` directly.
+pub fn bind_sampler(ctxt: &mut CommandContext, texture_unit: gl::types::GLuint,
+                    target: gl::types::GLenum, texture: gl::types::GLuint,
+                    samplers: &mut HashMap<SamplerBehavior, SamplerObject>,
+                    emulated_behaviors: &mut HashMap<gl::types::GLuint, SamplerBehavior>,
+                    behavior: &SamplerBehavior)
+{
+    match get_sampler_or_emulate(ctxt, samplers, behavior) {
+        SamplerResult::Object(id) => unsafe {
+            ctxt.gl.BindSampler(texture_unit, id);
+        },
+        SamplerResult::Emulated(behavior) => {
+            apply_emulated_sampler(ctxt, target, texture, emulated_behaviors, &behavior);
+        },
+    }
+}
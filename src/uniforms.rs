@@ -0,0 +1,242 @@
+use std::mem;
+use std::hash::{Hash, Hasher};
+
+use gl;
+use ToGlEnum;
+
+/// Function to use for the minify filter.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum MinifySamplerFilter {
+    /// Takes the nearest texel.
+    Nearest,
+    /// Linear interpolation between the texels of the nearest mipmap level.
+    Linear,
+    /// Same as `Nearest`, but also uses the nearest mipmap level.
+    NearestMipmapNearest,
+    /// Same as `Linear`, but also uses the nearest mipmap level.
+    LinearMipmapNearest,
+    /// Same as `Nearest`, but also linearly interpolates between the two nearest mipmap levels.
+    NearestMipmapLinear,
+    /// Same as `Linear`, but also linearly interpolates between the two nearest mipmap levels.
+    LinearMipmapLinear,
+}
+
+impl ToGlEnum for MinifySamplerFilter {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            MinifySamplerFilter::Nearest => gl::NEAREST,
+            MinifySamplerFilter::Linear => gl::LINEAR,
+            MinifySamplerFilter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
+            MinifySamplerFilter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
+            MinifySamplerFilter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
+            MinifySamplerFilter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+}
+
+/// Function to use for the magnify filter.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum MagnifySamplerFilter {
+    /// Takes the nearest texel.
+    Nearest,
+    /// Linearly interpolates between the nearest texels.
+    Linear,
+}
+
+impl ToGlEnum for MagnifySamplerFilter {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            MagnifySamplerFilter::Nearest => gl::NEAREST,
+            MagnifySamplerFilter::Linear => gl::LINEAR,
+        }
+    }
+}
+
+/// Describes how the texture coordinates should be wrapped when they are outside `[0; 1]`.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum SamplerWrapFunction {
+    /// Samples at coord `x + 1` are mapped to coord `x`.
+    Repeat,
+    /// Samples at coord `x + 1` are mapped to coord `1 - x`.
+    Mirror,
+    /// Samples at coord `x + 1` are mapped to coord `1`.
+    Clamp,
+    /// Samples at coord `x + 1` are mapped to `border_color`.
+    ///
+    /// Requires `SamplerBehavior::border_color` to be set, otherwise the driver's default
+    /// border color (transparent black) is used.
+    BorderClamp,
+}
+
+impl ToGlEnum for SamplerWrapFunction {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            SamplerWrapFunction::Repeat => gl::REPEAT,
+            SamplerWrapFunction::Mirror => gl::MIRRORED_REPEAT,
+            SamplerWrapFunction::Clamp => gl::CLAMP_TO_EDGE,
+            SamplerWrapFunction::BorderClamp => gl::CLAMP_TO_BORDER,
+        }
+    }
+}
+
+/// Comparison function to use when sampling a depth texture with a shadow sampler
+/// (eg. `sampler2DShadow` in GLSL).
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum DepthTexComparison {
+    /// Passes if `ref <= texel`, where `texel` is the depth stored in the texture.
+    LessOrEqual,
+    /// Passes if `ref >= texel`.
+    GreaterOrEqual,
+    /// Passes if `ref < texel`.
+    Less,
+    /// Passes if `ref > texel`.
+    Greater,
+    /// Passes if `ref == texel`.
+    Equal,
+    /// Passes if `ref != texel`.
+    NotEqual,
+    /// Always passes.
+    Always,
+    /// Never passes.
+    Never,
+}
+
+impl ToGlEnum for DepthTexComparison {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            DepthTexComparison::LessOrEqual => gl::LEQUAL,
+            DepthTexComparison::GreaterOrEqual => gl::GEQUAL,
+            DepthTexComparison::Less => gl::LESS,
+            DepthTexComparison::Greater => gl::GREATER,
+            DepthTexComparison::Equal => gl::EQUAL,
+            DepthTexComparison::NotEqual => gl::NOTEQUAL,
+            DepthTexComparison::Always => gl::ALWAYS,
+            DepthTexComparison::Never => gl::NEVER,
+        }
+    }
+}
+
+/// Behavior of a sampler.
+#[derive(Debug, Copy, Clone)]
+pub struct SamplerBehavior {
+    /// Functions to use for the X, Y, and Z coordinates.
+    pub wrap_function: (SamplerWrapFunction, SamplerWrapFunction, SamplerWrapFunction),
+    /// Filter to use when the texture is far away from the viewer.
+    pub minify_filter: MinifySamplerFilter,
+    /// Filter to use when the texture is close to the viewer.
+    pub magnify_filter: MagnifySamplerFilter,
+    /// `1` means no anisotropic filtering, any value superior to `1` sets the max anisotropy.
+    pub max_anisotropy: u16,
+    /// If set, the sampler performs hardware depth comparison (`sampler2DShadow` and
+    /// friends in GLSL) instead of returning the raw depth value.
+    pub depth_texture_comparison: Option<DepthTexComparison>,
+    /// Bias added to the computed level-of-detail before mipmap selection.
+    pub lod_bias: f32,
+    /// Lower bound of the computed level-of-detail.
+    pub min_lod: f32,
+    /// Upper bound of the computed level-of-detail.
+    pub max_lod: f32,
+    /// Color returned by `SamplerWrapFunction::BorderClamp`. `None` means that the driver's
+    /// default (transparent black) is used.
+    pub border_color: Option<(f32, f32, f32, f32)>,
+}
+
+impl Default for SamplerBehavior {
+    fn default() -> SamplerBehavior {
+        SamplerBehavior {
+            wrap_function: (SamplerWrapFunction::Mirror, SamplerWrapFunction::Mirror,
+                             SamplerWrapFunction::Mirror),
+            minify_filter: MinifySamplerFilter::LinearMipmapLinear,
+            magnify_filter: MagnifySamplerFilter::Linear,
+            max_anisotropy: 1,
+            depth_texture_comparison: None,
+            lod_bias: 0.0,
+            min_lod: -1000.0,
+            max_lod: 1000.0,
+            border_color: None,
+        }
+    }
+}
+
+impl PartialEq for SamplerBehavior {
+    fn eq(&self, other: &SamplerBehavior) -> bool {
+        self.wrap_function == other.wrap_function &&
+        self.minify_filter == other.minify_filter &&
+        self.magnify_filter == other.magnify_filter &&
+        self.max_anisotropy == other.max_anisotropy &&
+        self.depth_texture_comparison == other.depth_texture_comparison &&
+        bits_of_f32(self.lod_bias) == bits_of_f32(other.lod_bias) &&
+        bits_of_f32(self.min_lod) == bits_of_f32(other.min_lod) &&
+        bits_of_f32(self.max_lod) == bits_of_f32(other.max_lod) &&
+        self.border_color.map(bits_of_color) == other.border_color.map(bits_of_color)
+    }
+}
+
+impl Eq for SamplerBehavior {}
+
+impl Hash for SamplerBehavior {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.wrap_function.hash(state);
+        self.minify_filter.hash(state);
+        self.magnify_filter.hash(state);
+        self.max_anisotropy.hash(state);
+        self.depth_texture_comparison.hash(state);
+        // floats aren't `Hash`/`Eq`, so we hash their bit representation instead
+        bits_of_f32(self.lod_bias).hash(state);
+        bits_of_f32(self.min_lod).hash(state);
+        bits_of_f32(self.max_lod).hash(state);
+        self.border_color.map(bits_of_color).hash(state);
+    }
+}
+
+fn bits_of_f32(value: f32) -> u32 {
+    unsafe { mem::transmute(value) }
+}
+
+fn bits_of_color(color: (f32, f32, f32, f32)) -> (u32, u32, u32, u32) {
+    (bits_of_f32(color.0), bits_of_f32(color.1), bits_of_f32(color.2), bits_of_f32(color.3))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::{SamplerBehavior, DepthTexComparison};
+
+    #[test]
+    fn equal_behaviors_are_equal() {
+        let a = SamplerBehavior { lod_bias: 0.5, ..SamplerBehavior::default() };
+        let b = SamplerBehavior { lod_bias: 0.5, ..SamplerBehavior::default() };
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn differing_lod_bias_is_not_equal() {
+        let a = SamplerBehavior { lod_bias: 0.5, ..SamplerBehavior::default() };
+        let b = SamplerBehavior { lod_bias: 0.75, ..SamplerBehavior::default() };
+        assert!(a != b);
+    }
+
+    #[test]
+    fn differing_depth_comparison_is_not_equal() {
+        let a = SamplerBehavior::default();
+        let b = SamplerBehavior {
+            depth_texture_comparison: Some(DepthTexComparison::LessOrEqual),
+            ..SamplerBehavior::default()
+        };
+        assert!(a != b);
+    }
+
+    #[test]
+    fn dedupes_in_a_hash_set() {
+        let a = SamplerBehavior { lod_bias: 0.5, ..SamplerBehavior::default() };
+        let b = SamplerBehavior { lod_bias: 0.5, ..SamplerBehavior::default() };
+        let c = SamplerBehavior { lod_bias: 0.75, ..SamplerBehavior::default() };
+
+        let mut set = HashSet::new();
+        set.insert(a);
+        set.insert(b);
+        set.insert(c);
+
+        assert_eq!(set.len(), 2);
+    }
+}